@@ -1,13 +1,18 @@
 use anyhow::{bail, Context, Result};
 use core::time;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::fs::File;
 use std::io::{stdin, stdout, BufRead, BufReader, Result as IOResult, Stdout, Write};
 use std::path::Path;
 use std::thread::sleep;
+use rand::seq::SliceRandom;
+use rand::Rng;
 use termion::cursor::Goto;
 use termion::event::Key;
+use termion::{color, style};
 use termion::input::TermRead;
 use termion::raw::{IntoRawMode, RawTerminal};
 
@@ -32,15 +37,35 @@ const PUSH_MULTIPLE: bool = false;
 // initial size of undo history
 const HISTORY_SIZE: usize = 2000;
 
+// colorize the board; flip to false for terminals without color support
+const USE_COLOR: bool = true;
+
 // convention; first bunny in bunny vector is player
 const PLAYER_INDEX: usize = 0;
 
+// orthogonal steps as (row, column) deltas, in reading order
+const DIRS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
 // constants for the autosolver
 const MAX_MOVES: usize = 150;
 const MAX_ITERS: usize = 5000000;
 const SOLVER_STRUCTURE_SIZE: usize = 1024 * 1024;
 const SOLUTION_DISPLAY_SPEED: u64 = 60;
 
+// constants for the procedural level generator
+const TREE_PROBABILITY: f64 = 0.45;
+const SMOOTHING_PASSES: usize = 5;
+const GENERATE_ATTEMPTS: usize = 200;
+
+// frontier ordering for the autosolver
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Heuristic {
+    // plain breadth-first; h is always zero, so f == g
+    Bfs,
+    // A*; h is the summed box-to-bed Manhattan bound
+    AStar,
+}
+
 // possible moves
 enum Move {
     Up,
@@ -49,6 +74,18 @@ enum Move {
     Right,
 }
 
+impl Move {
+    /// the lowercase LURD letter for a plain step in this direction
+    fn letter(&self) -> char {
+        match self {
+            Move::Up => 'u',
+            Move::Down => 'd',
+            Move::Left => 'l',
+            Move::Right => 'r',
+        }
+    }
+}
+
 // possible tile states
 #[derive(Debug)]
 enum Tile {
@@ -67,17 +104,20 @@ type Bunny = (usize, usize);
 struct PreviousState {
     move_no: usize,
     bunnies: Vec<Bunny>,
+    moves: Vec<char>,
 }
 
 // game state
 struct State {
     board: Board,
     bunny_starts: Vec<Bunny>,
-    beds: HashSet<Bunny>, // bed positions; ignore that this is Bunny
-    bunnies: Vec<Bunny>,  // first bunny in this vector is player
+    beds: HashSet<Bunny>,         // bed positions; ignore that this is Bunny
+    dead_squares: HashSet<Bunny>, // non-bed cells from which a bunny can never reach a bed
+    bunnies: Vec<Bunny>,          // first bunny in this vector is player
     level_no: usize,
     move_no: usize,
     history: Vec<PreviousState>, // facilitates undo
+    moves: Vec<char>,            // realized moves in LURD notation
     won: bool,
 }
 
@@ -85,6 +125,102 @@ struct State {
 struct SolveStats {
     iters: usize,
     queue_len: usize,
+    pruned: usize,
+    heuristic: Heuristic,
+}
+
+// manhattan (taxicab) distance between two cells
+fn manhattan(a: Bunny, b: Bunny) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+// is (r, c) a wall for deadlock purposes? out-of-bounds counts as a tree
+fn is_wall(board: &Board, r: isize, c: isize) -> bool {
+    if r < 0 || c < 0 {
+        return true;
+    }
+    matches!(
+        board.get(r as usize).and_then(|row| row.get(c as usize)),
+        Some(Tile::Tree) | None
+    )
+}
+
+// precompute the "dead squares" for a level: non-bed floor cells from which a
+// pushed bunny can never reach a bed. corners (a wall on one axis and a wall on
+// the other) are dead, and the deadness propagates along a wall between two dead
+// corners when no bed sits in the run.
+fn compute_dead_squares(board: &Board, beds: &HashSet<Bunny>) -> HashSet<Bunny> {
+    let n_rows = board.len();
+    let n_columns = board.first().map(|row| row.len()).unwrap_or(0);
+
+    // corners first
+    let mut corners: HashSet<Bunny> = HashSet::new();
+    for (i, row) in board.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            if matches!(tile, Tile::Tree) || beds.contains(&(i, j)) {
+                continue;
+            }
+            let (r, c) = (i as isize, j as isize);
+            let vertical = is_wall(board, r - 1, c) || is_wall(board, r + 1, c);
+            let horizontal = is_wall(board, r, c - 1) || is_wall(board, r, c + 1);
+            if vertical && horizontal {
+                corners.insert((i, j));
+            }
+        }
+    }
+
+    let mut dead = corners.clone();
+
+    // propagate along rows: a run between two dead corners is dead when the
+    // whole run is bed-free floor backed by an unbroken wall above or below
+    for i in 0..n_rows {
+        for a in 0..n_columns {
+            if !corners.contains(&(i, a)) {
+                continue;
+            }
+            for b in (a + 1)..n_columns {
+                if !corners.contains(&(i, b)) {
+                    continue;
+                }
+                let run_ok = (a..=b)
+                    .all(|k| !matches!(board[i][k], Tile::Tree) && !beds.contains(&(i, k)));
+                if !run_ok {
+                    continue;
+                }
+                let above = (a..=b).all(|k| is_wall(board, i as isize - 1, k as isize));
+                let below = (a..=b).all(|k| is_wall(board, i as isize + 1, k as isize));
+                if above || below {
+                    dead.extend((a..=b).map(|k| (i, k)));
+                }
+            }
+        }
+    }
+
+    // propagate along columns, symmetrically
+    for j in 0..n_columns {
+        for a in 0..n_rows {
+            if !corners.contains(&(a, j)) {
+                continue;
+            }
+            for b in (a + 1)..n_rows {
+                if !corners.contains(&(b, j)) {
+                    continue;
+                }
+                let run_ok = (a..=b)
+                    .all(|k| !matches!(board[k][j], Tile::Tree) && !beds.contains(&(k, j)));
+                if !run_ok {
+                    continue;
+                }
+                let left = (a..=b).all(|k| is_wall(board, k as isize, j as isize - 1));
+                let right = (a..=b).all(|k| is_wall(board, k as isize, j as isize + 1));
+                if left || right {
+                    dead.extend((a..=b).map(|k| (k, j)));
+                }
+            }
+        }
+    }
+
+    dead
 }
 
 impl State {
@@ -95,6 +231,12 @@ impl State {
         let reader = BufReader::new(f);
 
         let board_rows: Vec<String> = reader.lines().collect::<IOResult<Vec<String>>>()?;
+        State::from_lines(board_rows, level_no)
+    }
+
+    /// create State object from the level's text lines and level number;
+    /// shared by `from_file` and the procedural generator
+    fn from_lines(board_rows: Vec<String>, level_no: usize) -> Result<State> {
         let mut board_raw: Vec<Vec<u8>> = board_rows
             .iter()
             .map(|row| row.as_bytes().to_vec())
@@ -157,14 +299,18 @@ impl State {
         bunnies.push(player);
         bunnies.reverse();
 
+        let dead_squares = compute_dead_squares(&board, &beds);
+
         Ok(State {
             board,
             bunnies: bunnies.clone(),
             bunny_starts: bunnies,
             beds,
+            dead_squares,
             level_no,
             move_no: 0,
             history: Vec::with_capacity(HISTORY_SIZE),
+            moves: Vec::with_capacity(HISTORY_SIZE),
             won: false,
         })
     }
@@ -175,6 +321,78 @@ impl State {
         State::from_file(format!("levels/{}.txt", level_no).as_str(), level_no)
     }
 
+    /// serialize the board back into the letter notation `from_file` accepts,
+    /// so generated levels round-trip through the normal loader
+    fn to_level_string(&self) -> String {
+        let player = self.bunnies[0];
+        let sleepies: HashSet<Bunny> = self.bunnies[1..].iter().copied().collect();
+        let mut out = String::new();
+        for (i, row) in self.board.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                let glyph = match tile {
+                    Tile::Tree => 'T',
+                    Tile::Grass => {
+                        if (i, j) == player {
+                            'b'
+                        } else if sleepies.contains(&(i, j)) {
+                            's'
+                        } else {
+                            ' '
+                        }
+                    }
+                    Tile::Bed => {
+                        if (i, j) == player {
+                            'p'
+                        } else if sleepies.contains(&(i, j)) {
+                            'z'
+                        } else {
+                            '_'
+                        }
+                    }
+                };
+                out.push(glyph);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// the realized solution so far as a canonical LURD move string
+    fn lurd(&self) -> String {
+        self.moves.iter().collect()
+    }
+
+    /// replay a LURD move string against a freshly loaded level, validating each
+    /// move through `move_bunny`; reports the first character that is not a legal
+    /// move (an unknown letter, a blocked step, or a wrong push/step case)
+    fn replay(level_no: usize, lurd: &str) -> Result<State> {
+        let mut state = State::from_level_no(level_no)?;
+        for (i, ch) in lurd.chars().enumerate() {
+            let m = match ch.to_ascii_lowercase() {
+                'u' => Move::Up,
+                'd' => Move::Down,
+                'l' => Move::Left,
+                'r' => Move::Right,
+                _ => bail!("illegal character {:?} at position {}", ch, i),
+            };
+            if !state.move_bunny(PLAYER_INDEX, m) {
+                bail!("blocked move {:?} at position {}", ch, i);
+            }
+            // the recorded letter encodes whether a push happened, so a lowercase
+            // letter where a push occurred (or vice versa) is a mismatch
+            let realized = *state.moves.last().expect("a move was just recorded");
+            if realized != ch {
+                bail!(
+                    "move {:?} at position {} should have been {:?}",
+                    ch,
+                    i,
+                    realized
+                );
+            }
+        }
+        Ok(state)
+    }
+
     /// move bunny. returns false if not moved (for instance,
     /// if bunny tried to hop into a tree)
     fn move_bunny(&mut self, bunny_index: usize, m: Move) -> bool {
@@ -194,12 +412,17 @@ impl State {
             return false;
         }
 
+        // LURD letter for this step, captured before `m` moves into recursion
+        let letter = m.letter();
+
         match self.board[dest.0][dest.1] {
             Tile::Tree => false,
             Tile::Grass | Tile::Bed => {
                 let current_state = self.bunnies.clone();
+                let current_moves = self.moves.clone();
 
                 // push sleepy bunny
+                let mut pushed = false;
                 if let Some(bunny_in_the_way_index) =
                     self.bunnies.iter().position(|bunny| dest == *bunny)
                 {
@@ -211,6 +434,7 @@ impl State {
                     if !self.move_bunny(bunny_in_the_way_index, m) {
                         return false;
                     }
+                    pushed = true;
                 }
 
                 // move player bunny
@@ -218,8 +442,15 @@ impl State {
                     self.history.push(PreviousState {
                         move_no: self.move_no,
                         bunnies: current_state,
+                        moves: current_moves,
                     });
                     self.move_no += 1;
+                    // uppercase marks a step that pushed a sleepy bunny
+                    self.moves.push(if pushed {
+                        letter.to_ascii_uppercase()
+                    } else {
+                        letter
+                    });
                 }
                 self.bunnies[bunny_index] = dest;
                 true
@@ -293,31 +524,70 @@ impl State {
         sleepies_pos.iter().all(|pos| self.beds.contains(pos))
     }
 
-    /// backtracking solver for state; the interesting part of this program,
-    /// programming-wise. never uses undo or reset, so starts from current state
+    /// admissible lower bound on the remaining player moves: the summed
+    /// Manhattan distance from every sleepy bunny to its nearest bed. each
+    /// push advances one bunny one tile for at least one player move, so this
+    /// never overestimates. combined with reopening (see `solve`), that keeps
+    /// the first win popped optimal. the Bfs heuristic returns zero, collapsing
+    /// solve back to plain breadth-first.
+    fn heuristic_cost(&self, bunnies: &[Bunny], heuristic: Heuristic) -> usize {
+        match heuristic {
+            Heuristic::Bfs => 0,
+            Heuristic::AStar => bunnies
+                .get(1..bunnies.len())
+                .expect("sleepies should be there")
+                .iter()
+                .map(|sleepy| {
+                    self.beds
+                        .iter()
+                        .map(|bed| manhattan(*sleepy, *bed))
+                        .min()
+                        .unwrap_or(0)
+                })
+                .sum(),
+        }
+    }
+
+    /// best-first solver for state; the interesting part of this program,
+    /// programming-wise. never uses undo or reset, so starts from current state.
+    /// orders the frontier by f = g + h, where g is the search depth (the player
+    /// move count plus one, as the root starts at 1) and h is `heuristic_cost`; with Heuristic::Bfs this degenerates to plain BFS.
+    /// a node is closed only when popped, and a node reached by a cheaper g is
+    /// reopened (the stale heap entry is discarded on pop), so with an
+    /// admissible h the first win popped is genuinely optimal
     fn solve(
         state: &State,
         max_moves: usize,
         max_iters: usize,
+        heuristic: Heuristic,
     ) -> Option<(Vec<Vec<Bunny>>, SolveStats)> {
         // storage of previous states
         let mut states: Vec<Vec<Bunny>> = Vec::with_capacity(SOLVER_STRUCTURE_SIZE);
         let mut visited: HashMap<Vec<Bunny>, usize> = HashMap::with_capacity(SOLVER_STRUCTURE_SIZE);
 
-        // all of these use indices for 'states'
-        let mut queue: VecDeque<usize> = VecDeque::with_capacity(SOLVER_STRUCTURE_SIZE);
+        // all of these use indices for 'states'; the frontier is a min-heap on
+        // (f, g), smallest first (hence the Reverse wrapper). carrying g in the
+        // entry lets a pop detect whether it has been superseded by a cheaper one
+        let mut queue: BinaryHeap<Reverse<(usize, usize, usize)>> =
+            BinaryHeap::with_capacity(SOLVER_STRUCTURE_SIZE);
         let mut parents: HashMap<usize, usize> = HashMap::with_capacity(SOLVER_STRUCTURE_SIZE);
         let mut depths: HashMap<usize, usize> = HashMap::with_capacity(SOLVER_STRUCTURE_SIZE);
 
         let mut iters = 0;
+        let mut pruned = 0;
 
         states.push(state.bunnies.clone());
         visited.insert(state.bunnies.clone(), 0);
-        queue.push_back(0);
+        let root_h = state.heuristic_cost(&state.bunnies, heuristic);
+        queue.push(Reverse((1 + root_h, 1, 0)));
         depths.insert(0, 1);
 
         let mut winning_index_opt = None;
-        while let Some(new_index) = queue.pop_front() {
+        while let Some(Reverse((_f, g, new_index))) = queue.pop() {
+            // skip stale entries left behind when this node was reopened cheaper
+            if g > depths[&new_index] {
+                continue;
+            }
             iters += 1;
             let new_state = states[new_index].clone();
             if state.check_win_solve(&new_state) {
@@ -341,13 +611,33 @@ impl State {
                         None => continue,
                     };
 
-                if !visited.contains_key(&candidate_state) {
-                    states.push(candidate_state.clone());
-                    let candidate_index = states.len() - 1;
-                    visited.insert(candidate_state, candidate_index);
-                    queue.push_back(candidate_index);
-                    parents.insert(candidate_index, new_index);
-                    depths.insert(candidate_index, depths[&new_index] + 1);
+                // cut candidates that have pushed a bunny into a dead branch
+                if state.is_deadlock(&candidate_state[1..]) {
+                    pruned += 1;
+                    continue;
+                }
+
+                let g = depths[&new_index] + 1;
+                let h = state.heuristic_cost(&candidate_state, heuristic);
+                match visited.entry(candidate_state) {
+                    // first time we reach this state
+                    Entry::Vacant(slot) => {
+                        let candidate_index = states.len();
+                        states.push(slot.key().clone());
+                        slot.insert(candidate_index);
+                        parents.insert(candidate_index, new_index);
+                        depths.insert(candidate_index, g);
+                        queue.push(Reverse((g + h, g, candidate_index)));
+                    }
+                    // seen before; reopen only if this path is strictly cheaper
+                    Entry::Occupied(slot) => {
+                        let candidate_index = *slot.get();
+                        if g < depths[&candidate_index] {
+                            parents.insert(candidate_index, new_index);
+                            depths.insert(candidate_index, g);
+                            queue.push(Reverse((g + h, g, candidate_index)));
+                        }
+                    }
                 }
             }
         }
@@ -371,11 +661,369 @@ impl State {
             SolveStats {
                 iters,
                 queue_len: queue.len(),
+                pruned,
+                heuristic,
+            },
+        ))
+    }
+
+    /// the in-bounds cell reached by shifting `cell` by (dr, dc), if any
+    fn shift(&self, cell: Bunny, dr: isize, dc: isize) -> Option<Bunny> {
+        let r = cell.0.checked_add_signed(dr)?;
+        let c = cell.1.checked_add_signed(dc)?;
+        if c >= self.board.get(r)?.len() {
+            return None;
+        }
+        Some((r, c))
+    }
+
+    /// cells reachable from `player` by orthogonal steps over non-tree tiles,
+    /// treating the sleepy bunnies as walls; the flood fill that lets the push
+    /// solver forget where the idle player happens to be standing
+    fn reachable(&self, player: Bunny, sleepies: &HashSet<Bunny>) -> HashSet<Bunny> {
+        let mut seen: HashSet<Bunny> = HashSet::new();
+        let mut queue: VecDeque<Bunny> = VecDeque::new();
+        seen.insert(player);
+        queue.push_back(player);
+        while let Some(cell) = queue.pop_front() {
+            for (dr, dc) in DIRS {
+                let next = match self.shift(cell, dr, dc) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                if seen.contains(&next)
+                    || sleepies.contains(&next)
+                    || matches!(self.board[next.0][next.1], Tile::Tree)
+                {
+                    continue;
+                }
+                seen.insert(next);
+                queue.push_back(next);
+            }
+        }
+        seen
+    }
+
+    /// the topmost-then-leftmost cell reachable from `player`; the canonical
+    /// representative shared by every player position in the same region
+    fn normalize(&self, player: Bunny, sleepies: &HashSet<Bunny>) -> Bunny {
+        self.reachable(player, sleepies)
+            .into_iter()
+            .min()
+            .expect("the player's own cell is always reachable")
+    }
+
+    /// is the bunny at `pos` immovable along one axis? a wall or board edge on
+    /// either side pins it, as do dead squares on both sides; a neighbouring
+    /// bunny pins it when that neighbour is itself pinned on the other axis
+    /// (with `pos` held as a wall to break the mutual recursion)
+    fn blocked_axis(
+        &self,
+        pos: Bunny,
+        horizontal: bool,
+        boxes: &HashSet<Bunny>,
+        pinned: &mut HashSet<Bunny>,
+    ) -> bool {
+        let sides = if horizontal {
+            [self.shift(pos, 0, -1), self.shift(pos, 0, 1)]
+        } else {
+            [self.shift(pos, -1, 0), self.shift(pos, 1, 0)]
+        };
+        // a wall or the board edge on either side
+        if sides.iter().any(|side| match side {
+            None => true,
+            Some(c) => matches!(self.board[c.0][c.1], Tile::Tree),
+        }) {
+            return true;
+        }
+        // dead squares on both sides leave nowhere recoverable to go
+        if sides
+            .iter()
+            .all(|side| matches!(side, Some(c) if self.dead_squares.contains(c)))
+        {
+            return true;
+        }
+        // a neighbouring bunny, pinned on the perpendicular axis
+        pinned.insert(pos);
+        let mut blocked = false;
+        for side in sides.into_iter().flatten() {
+            if boxes.contains(&side)
+                && (pinned.contains(&side) || self.blocked_axis(side, !horizontal, boxes, pinned))
+            {
+                blocked = true;
+                break;
+            }
+        }
+        pinned.remove(&pos);
+        blocked
+    }
+
+    /// a bunny is frozen when it is pinned along both axes at once
+    fn frozen(&self, pos: Bunny, boxes: &HashSet<Bunny>) -> bool {
+        let mut pinned: HashSet<Bunny> = HashSet::new();
+        self.blocked_axis(pos, true, boxes, &mut pinned)
+            && self.blocked_axis(pos, false, boxes, &mut pinned)
+    }
+
+    /// is this layout of sleepy bunnies already unwinnable? true when any
+    /// off-bed bunny sits on a dead square or is frozen in place. the solvers
+    /// consult this before enqueueing a candidate so dead branches are cut
+    fn is_deadlock(&self, sleepies: &[Bunny]) -> bool {
+        if sleepies
+            .iter()
+            .any(|b| !self.beds.contains(b) && self.dead_squares.contains(b))
+        {
+            return true;
+        }
+        let boxes: HashSet<Bunny> = sleepies.iter().copied().collect();
+        sleepies
+            .iter()
+            .any(|&b| !self.beds.contains(&b) && self.frozen(b, &boxes))
+    }
+
+    /// shortest orthogonal path of player cells from `start` to `goal` over
+    /// non-tree tiles (sleepies as walls), excluding `start`; None if blocked
+    fn player_path(
+        &self,
+        start: Bunny,
+        goal: Bunny,
+        sleepies: &HashSet<Bunny>,
+    ) -> Option<Vec<Bunny>> {
+        let mut parents: HashMap<Bunny, Bunny> = HashMap::new();
+        let mut queue: VecDeque<Bunny> = VecDeque::new();
+        parents.insert(start, start);
+        queue.push_back(start);
+        while let Some(cell) = queue.pop_front() {
+            if cell == goal {
+                let mut path: Vec<Bunny> = Vec::new();
+                let mut cur = goal;
+                while cur != start {
+                    path.push(cur);
+                    cur = parents[&cur];
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for (dr, dc) in DIRS {
+                let next = match self.shift(cell, dr, dc) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                if parents.contains_key(&next)
+                    || sleepies.contains(&next)
+                    || matches!(self.board[next.0][next.1], Tile::Tree)
+                {
+                    continue;
+                }
+                parents.insert(next, cell);
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// push-based solver. a search node is the sorted sleepy-bunny positions
+    /// plus the normalized player cell, so states that differ only in where the
+    /// idle player stands collapse together and the state space shrinks
+    /// dramatically. transitions are macro-pushes: the player walks (off-node)
+    /// to the tile opposite a bunny and shoves it one tile. the returned path is
+    /// the concrete per-step bunny layout, rebuilt with a per-segment player BFS
+    /// between consecutive nodes so playback still shows every arrow-key step
+    fn solve_push(state: &State, max_iters: usize) -> Option<(Vec<Vec<Bunny>>, SolveStats)> {
+        // a node is (sorted sleepy positions, normalized player cell)
+        type Node = (Vec<Bunny>, Bunny);
+
+        let init_sleepies: Vec<Bunny> = {
+            let mut v = state.bunnies[1..].to_vec();
+            v.sort();
+            v
+        };
+        let init_player = state.bunnies[0];
+
+        let mut nodes: Vec<Node> = Vec::with_capacity(SOLVER_STRUCTURE_SIZE);
+        let mut visited: HashMap<Node, usize> = HashMap::with_capacity(SOLVER_STRUCTURE_SIZE);
+        let mut queue: VecDeque<usize> = VecDeque::with_capacity(SOLVER_STRUCTURE_SIZE);
+        // child index -> (parent index, stand cell, bunny-from cell, bunny-to cell)
+        let mut transitions: HashMap<usize, (usize, Bunny, Bunny, Bunny)> =
+            HashMap::with_capacity(SOLVER_STRUCTURE_SIZE);
+
+        let root_sleepies: HashSet<Bunny> = init_sleepies.iter().copied().collect();
+        let root: Node = (
+            init_sleepies.clone(),
+            state.normalize(init_player, &root_sleepies),
+        );
+        nodes.push(root.clone());
+        visited.insert(root, 0);
+        queue.push_back(0);
+
+        let mut iters = 0;
+        let mut pruned = 0;
+        let mut winning_index_opt = None;
+        while let Some(index) = queue.pop_front() {
+            iters += 1;
+            let (sleepies, player_norm) = nodes[index].clone();
+
+            if sleepies.iter().all(|pos| state.beds.contains(pos)) {
+                winning_index_opt = Some(index);
+                break;
+            }
+            if iters == max_iters {
+                break;
+            }
+
+            let occupied: HashSet<Bunny> = sleepies.iter().copied().collect();
+            let reachable = state.reachable(player_norm, &occupied);
+
+            for (i, &bunny) in sleepies.iter().enumerate() {
+                for (dr, dc) in DIRS {
+                    let to = match state.shift(bunny, dr, dc) {
+                        Some(to) => to,
+                        None => continue,
+                    };
+                    let stand = match state.shift(bunny, -dr, -dc) {
+                        Some(stand) => stand,
+                        None => continue,
+                    };
+                    // the player must be able to reach the pushing tile, and the
+                    // destination must be an unoccupied, non-tree tile
+                    if !reachable.contains(&stand)
+                        || occupied.contains(&to)
+                        || matches!(state.board[to.0][to.1], Tile::Tree)
+                    {
+                        continue;
+                    }
+
+                    let mut new_sleepies = sleepies.clone();
+                    new_sleepies[i] = to;
+                    new_sleepies.sort();
+                    // cut pushes that land in a dead branch before enqueueing
+                    if state.is_deadlock(&new_sleepies) {
+                        pruned += 1;
+                        continue;
+                    }
+                    let new_occupied: HashSet<Bunny> = new_sleepies.iter().copied().collect();
+                    // the player ends on the bunny's old cell, then renormalizes
+                    let child: Node = (new_sleepies, state.normalize(bunny, &new_occupied));
+
+                    if let Entry::Vacant(slot) = visited.entry(child) {
+                        let child_index = nodes.len();
+                        nodes.push(slot.key().clone());
+                        slot.insert(child_index);
+                        queue.push_back(child_index);
+                        transitions.insert(child_index, (index, stand, bunny, to));
+                    }
+                }
+            }
+        }
+
+        let winning_index = winning_index_opt?;
+
+        // collect the macro-push chain from root to the winning node
+        let mut chain: Vec<(Bunny, Bunny, Bunny)> = Vec::new();
+        let mut cursor = winning_index;
+        while let Some(&(parent, stand, from, to)) = transitions.get(&cursor) {
+            chain.push((stand, from, to));
+            cursor = parent;
+        }
+        chain.reverse();
+
+        // replay the chain concretely, rebuilding every intermediate step
+        let layout = |player: Bunny, sleepies: &[Bunny]| -> Vec<Bunny> {
+            let mut v = Vec::with_capacity(sleepies.len() + 1);
+            v.push(player);
+            v.extend_from_slice(sleepies);
+            v
+        };
+        let mut path: Vec<Vec<Bunny>> = Vec::new();
+        let mut player = init_player;
+        let mut sleepies = init_sleepies;
+        path.push(layout(player, &sleepies));
+        for (stand, from, to) in chain {
+            let blocked: HashSet<Bunny> = sleepies.iter().copied().collect();
+            let walk = state
+                .player_path(player, stand, &blocked)
+                .expect("a macro push's stand tile is reachable by construction");
+            for cell in walk {
+                player = cell;
+                path.push(layout(player, &sleepies));
+            }
+            // the push itself: player steps onto the bunny's old cell as it moves
+            player = from;
+            if let Some(slot) = sleepies.iter_mut().find(|s| **s == from) {
+                *slot = to;
+            }
+            path.push(layout(player, &sleepies));
+        }
+
+        Some((
+            path,
+            SolveStats {
+                iters,
+                queue_len: queue.len(),
+                pruned,
+                heuristic: Heuristic::Bfs,
             },
         ))
     }
 }
 
+// the glyph and foreground/background colors for one board cell. layout is
+// separated from color the way the roguelike display module does it: the caller
+// works out what occupies the cell from its position, this picks the glyph and
+// how to paint it
+fn cell_style(
+    tile: &Tile,
+    is_player: bool,
+    is_sleepy: bool,
+) -> (char, Box<dyn fmt::Display>, Box<dyn fmt::Display>) {
+    if is_player {
+        match tile {
+            Tile::Bed => (
+                'p',
+                Box::new(color::Fg(color::Yellow)),
+                Box::new(color::Bg(color::Cyan)),
+            ),
+            _ => (
+                'b',
+                Box::new(color::Fg(color::Yellow)),
+                Box::new(color::Bg(color::Reset)),
+            ),
+        }
+    } else if is_sleepy {
+        match tile {
+            // a sleepy already resting on a bed gets the "solved" color
+            Tile::Bed => (
+                'z',
+                Box::new(color::Fg(color::Black)),
+                Box::new(color::Bg(color::LightGreen)),
+            ),
+            _ => (
+                's',
+                Box::new(color::Fg(color::Magenta)),
+                Box::new(color::Bg(color::Reset)),
+            ),
+        }
+    } else {
+        match tile {
+            Tile::Tree => (
+                'T',
+                Box::new(color::Fg(color::Green)),
+                Box::new(color::Bg(color::Reset)),
+            ),
+            Tile::Bed => (
+                '_',
+                Box::new(color::Fg(color::Cyan)),
+                Box::new(color::Bg(color::Reset)),
+            ),
+            Tile::Grass => (
+                ' ',
+                Box::new(color::Fg(color::Reset)),
+                Box::new(color::Bg(color::Reset)),
+            ),
+        }
+    }
+}
+
 // custom print
 impl fmt::Display for State {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -389,30 +1037,25 @@ impl fmt::Display for State {
 
         for (i, row) in self.board.iter().enumerate() {
             for (j, column) in row.iter().enumerate() {
-                // board
-                let tile = match column {
-                    Tile::Tree => 'T',
-                    Tile::Grass => {
-                        if (i, j) == *player_pos {
-                            'b'
-                        } else if sleepies_pos.contains(&(i, j)) {
-                            's'
-                        } else {
-                            ' '
-                        }
-                    }
-                    Tile::Bed => {
-                        if (i, j) == *player_pos {
-                            'p'
-                        } else if sleepies_pos.contains(&(i, j)) {
-                            'z'
-                        } else {
-                            '_'
-                        }
-                    }
-                };
+                let is_player = (i, j) == *player_pos;
+                let is_sleepy = sleepies_pos.contains(&(i, j));
+                let (glyph, fg, bg) = cell_style(column, is_player, is_sleepy);
 
-                write!(f, "{}", tile)?;
+                if !USE_COLOR {
+                    write!(f, "{}", glyph)?;
+                } else if self.won {
+                    // flash the whole board in the solved color on a win
+                    write!(
+                        f,
+                        "{}{}{}{}",
+                        color::Fg(color::Black),
+                        color::Bg(color::Green),
+                        glyph,
+                        style::Reset
+                    )?;
+                } else {
+                    write!(f, "{}{}{}{}", fg, bg, glyph, style::Reset)?;
+                }
             }
             writeln!(f, "\r")?;
         }
@@ -425,12 +1068,222 @@ impl fmt::Display for State {
         }
         writeln!(f, "arrow keys to move, z to undo, r to reset.\r")?;
         writeln!(f, "b for last level, n for next level, q to quit.\r")?;
-        writeln!(f, "press s if you'd like to see the bunny try it.\n\r")?;
+        writeln!(f, "press s if you'd like to see the bunny try it.\r")?;
+        writeln!(f, "press a to race plain BFS against A*.\r")?;
+        writeln!(f, "press c to print the solution in LURD notation.\n\r")?;
 
         fmt::Result::Ok(())
     }
 }
 
+// seed a cave with cellular-automata smoothing: interior cells start as trees
+// with probability TREE_PROBABILITY, then each pass turns a cell into a tree
+// when five or more of its eight neighbors are trees. the border stays trees.
+fn cave_grid<R: Rng>(n_rows: usize, n_columns: usize, rng: &mut R) -> Vec<Vec<bool>> {
+    let mut grid = vec![vec![true; n_columns]; n_rows];
+    for (i, row) in grid.iter_mut().enumerate() {
+        if i == 0 || i + 1 >= n_rows {
+            continue;
+        }
+        for (j, cell) in row.iter_mut().enumerate() {
+            if j == 0 || j + 1 >= n_columns {
+                continue;
+            }
+            *cell = rng.gen_bool(TREE_PROBABILITY);
+        }
+    }
+    for _ in 0..SMOOTHING_PASSES {
+        let mut next = grid.clone();
+        for i in 0..n_rows {
+            for j in 0..n_columns {
+                if i == 0 || j == 0 || i == n_rows - 1 || j == n_columns - 1 {
+                    next[i][j] = true;
+                    continue;
+                }
+                let mut trees = 0;
+                for di in -1..=1isize {
+                    for dj in -1..=1isize {
+                        if di == 0 && dj == 0 {
+                            continue;
+                        }
+                        if grid[(i as isize + di) as usize][(j as isize + dj) as usize] {
+                            trees += 1;
+                        }
+                    }
+                }
+                next[i][j] = trees >= 5;
+            }
+        }
+        grid = next;
+    }
+    grid
+}
+
+// retain only the largest connected grass region, editing the grid in place;
+// every other grass cell (true means tree) is filled back in so the level is one cave
+fn keep_largest_region(grid: &mut [Vec<bool>]) {
+    let n_rows = grid.len();
+    let n_columns = grid.first().map(|row| row.len()).unwrap_or(0);
+
+    let mut seen = vec![vec![false; n_columns]; n_rows];
+    let mut best: Vec<Bunny> = Vec::new();
+    for i in 0..n_rows {
+        for j in 0..n_columns {
+            if grid[i][j] || seen[i][j] {
+                continue;
+            }
+            let mut region: Vec<Bunny> = Vec::new();
+            let mut queue: VecDeque<Bunny> = VecDeque::new();
+            seen[i][j] = true;
+            queue.push_back((i, j));
+            while let Some((r, c)) = queue.pop_front() {
+                region.push((r, c));
+                for (dr, dc) in DIRS {
+                    let nr = r as isize + dr;
+                    let nc = c as isize + dc;
+                    if nr < 0 || nc < 0 || nr as usize >= n_rows || nc as usize >= n_columns {
+                        continue;
+                    }
+                    let (nr, nc) = (nr as usize, nc as usize);
+                    if !grid[nr][nc] && !seen[nr][nc] {
+                        seen[nr][nc] = true;
+                        queue.push_back((nr, nc));
+                    }
+                }
+            }
+            if region.len() > best.len() {
+                best = region;
+            }
+        }
+    }
+
+    let keep: HashSet<Bunny> = best.into_iter().collect();
+    for (i, row) in grid.iter_mut().enumerate() {
+        for (j, cell) in row.iter_mut().enumerate() {
+            if !keep.contains(&(i, j)) {
+                *cell = true;
+            }
+        }
+    }
+}
+
+// render a grid plus entity placements into the loader's letter notation
+fn grid_to_lines(
+    grid: &[Vec<bool>],
+    player: Bunny,
+    sleepies: &HashSet<Bunny>,
+    beds: &HashSet<Bunny>,
+) -> Vec<String> {
+    grid.iter()
+        .enumerate()
+        .map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(|(j, &tree)| {
+                    let on_bed = beds.contains(&(i, j));
+                    if tree {
+                        'T'
+                    } else if (i, j) == player {
+                        if on_bed {
+                            'p'
+                        } else {
+                            'b'
+                        }
+                    } else if sleepies.contains(&(i, j)) {
+                        if on_bed {
+                            'z'
+                        } else {
+                            's'
+                        }
+                    } else if on_bed {
+                        '_'
+                    } else {
+                        ' '
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// synthesize a fresh, solvable level: grow a cave, keep its largest region,
+// scatter the player, k sleepy bunnies, and k beds, then validate by solving.
+// retries until a solvable layout turns up or the attempt budget runs out
+fn generate<R: Rng>(
+    n_rows: usize,
+    n_columns: usize,
+    k: usize,
+    max_iters: usize,
+    rng: &mut R,
+) -> Result<State> {
+    for _ in 0..GENERATE_ATTEMPTS {
+        let mut grid = cave_grid(n_rows, n_columns, rng);
+        keep_largest_region(&mut grid);
+
+        let mut grass: Vec<Bunny> = grid
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, &tree)| !tree)
+                    .map(move |(j, _)| (i, j))
+            })
+            .collect();
+        if grass.len() < 2 * k + 1 {
+            continue;
+        }
+        grass.shuffle(rng);
+
+        let player = grass[0];
+        let sleepies: HashSet<Bunny> = grass[1..=k].iter().copied().collect();
+        let beds: HashSet<Bunny> = grass[k + 1..=2 * k].iter().copied().collect();
+
+        let lines = grid_to_lines(&grid, player, &sleepies, &beds);
+        let state = match State::from_lines(lines, 0) {
+            Ok(state) => state,
+            Err(_) => continue,
+        };
+        if State::solve(&state, MAX_MOVES, max_iters, Heuristic::AStar).is_some() {
+            return Ok(state);
+        }
+    }
+    bail!(
+        "couldn't generate a solvable level in {} attempts",
+        GENERATE_ATTEMPTS
+    );
+}
+
+// `generate <rows> <columns> <bunnies> <out>` subcommand entry point
+fn run_generate(args: &[String]) -> Result<()> {
+    let usage = "usage: generate <rows> <columns> <bunnies> <out>";
+    let n_rows: usize = args.get(2).context(usage)?.parse().context(usage)?;
+    let n_columns: usize = args.get(3).context(usage)?.parse().context(usage)?;
+    let k: usize = args.get(4).context(usage)?.parse().context(usage)?;
+    let out = args.get(5).context(usage)?;
+
+    let mut rng = rand::thread_rng();
+    let state = generate(n_rows, n_columns, k, MAX_ITERS, &mut rng)?;
+
+    let mut f = File::create(out)?;
+    f.write_all(state.to_level_string().as_bytes())?;
+    println!("wrote a solvable {}x{} level to {}", n_rows, n_columns, out);
+    Ok(())
+}
+
+// `replay <level> <lurd>` subcommand: replay a shared solution string against a
+// freshly loaded level, printing the final board (or the offending move)
+fn run_replay(args: &[String]) -> Result<()> {
+    let usage = "usage: replay <level> <lurd>";
+    let level_no: usize = args.get(2).context(usage)?.parse().context(usage)?;
+    let lurd = args.get(3).context(usage)?;
+
+    let mut state = State::replay(level_no, lurd)?;
+    state.check_win();
+    print!("{}", state);
+    Ok(())
+}
+
 // update any state metadata and print
 fn display(state: &mut State, stdout: &mut RawTerminal<Stdout>) -> Result<()> {
     state.check_win();
@@ -442,6 +1295,15 @@ fn display(state: &mut State, stdout: &mut RawTerminal<Stdout>) -> Result<()> {
 
 // main control loop
 fn main() -> Result<()> {
+    // offline `generate` and `replay` subcommands; otherwise fall through to the game
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("generate") {
+        return run_generate(&args);
+    }
+    if args.get(1).map(String::as_str) == Some("replay") {
+        return run_replay(&args);
+    }
+
     // get stdin and stdout in raw mode;
     // incidentally, raw mode means you need to input carriage returns manually
     let stdin = stdin();
@@ -469,17 +1331,26 @@ fn main() -> Result<()> {
                 state.history.push(PreviousState {
                     move_no: state.move_no,
                     bunnies: state.bunnies.clone(),
+                    moves: state.moves.clone(),
                 });
                 state.bunnies.clone_from(&state.bunny_starts);
                 state.move_no = 0;
+                state.moves.clear();
             }
             // undo
             Key::Char('z') => {
                 if let Some(last_state) = state.history.pop() {
                     state.bunnies.clone_from(&last_state.bunnies);
                     state.move_no = last_state.move_no;
+                    state.moves = last_state.moves;
                 }
             }
+            // dump the current solution as a LURD move string
+            Key::Char('c') => {
+                writeln!(stdout, "{}\r", state.lurd())?;
+                stdout.flush()?;
+                continue;
+            }
             // back one level
             Key::Char('b') => {
                 state = State::from_level_no(state.level_no.saturating_sub(1))?;
@@ -491,7 +1362,7 @@ fn main() -> Result<()> {
             }
             // solve
             Key::Char('s') => {
-                let soln = match State::solve(&state, MAX_MOVES, MAX_ITERS) {
+                let soln = match State::solve_push(&state, MAX_ITERS) {
                     Some(soln) => soln,
                     None => {
                         writeln!(stdout, "the bunny couldn't do it from here.\r")?;
@@ -507,6 +1378,7 @@ fn main() -> Result<()> {
                     sleep(time::Duration::from_millis(SOLUTION_DISPLAY_SPEED));
                 }
                 writeln!(stdout, "iters: {}.\r", soln.1.iters)?;
+                writeln!(stdout, "deadlocks pruned: {}.\r", soln.1.pruned)?;
                 writeln!(
                     stdout,
                     "states left in data structure: {}.\r",
@@ -515,6 +1387,40 @@ fn main() -> Result<()> {
                 stdout.flush()?;
                 continue;
             }
+            // solve, comparing the plain BFS frontier against the A* one
+            Key::Char('a') => {
+                let bfs = State::solve(&state, MAX_MOVES, MAX_ITERS, Heuristic::Bfs);
+                let astar = State::solve(&state, MAX_MOVES, MAX_ITERS, Heuristic::AStar);
+                let soln = match &astar {
+                    Some(soln) => soln,
+                    None => {
+                        writeln!(stdout, "the bunny couldn't do it from here.\r")?;
+                        stdout.flush()?;
+                        continue;
+                    }
+                };
+
+                for elt in &soln.0 {
+                    state.bunnies.clone_from(elt);
+                    display(&mut state, &mut stdout)?;
+                    state.move_no += 1;
+                    sleep(time::Duration::from_millis(SOLUTION_DISPLAY_SPEED));
+                }
+                if let Some(bfs) = &bfs {
+                    writeln!(
+                        stdout,
+                        "{:?} iters: {} (pruned {}).\r",
+                        bfs.1.heuristic, bfs.1.iters, bfs.1.pruned
+                    )?;
+                }
+                writeln!(
+                    stdout,
+                    "{:?} iters: {} (pruned {}).\r",
+                    soln.1.heuristic, soln.1.iters, soln.1.pruned
+                )?;
+                stdout.flush()?;
+                continue;
+            }
             // quit
             Key::Char('q') => break,
             _ => continue,